@@ -1,23 +1,267 @@
-use zed_extension_api::{Command, Extension, LanguageServerId, Worktree};
+use std::fs;
+use std::path::PathBuf;
 
-struct TypedownExtension;
+use zed_extension_api::lsp::{Completion, CompletionKind, Symbol, SymbolKind};
+use zed_extension_api::settings::LspSettings;
+use zed_extension_api::{
+    self as zed, Architecture, CodeLabel, CodeLabelSpan, Command, DownloadedFileType, Extension,
+    GithubReleaseOptions, LanguageServerId, LanguageServerInstallationStatus, Os, Result, Worktree,
+};
+
+const GITHUB_REPO: &str = "IndenScale/Typedown";
+
+struct TypedownExtension {
+    cached_binary_path: Option<String>,
+}
+
+impl TypedownExtension {
+    /// Resolves the `typedown` executable, preferring a project-local virtualenv,
+    /// then `$PATH`, and finally a prebuilt binary downloaded from GitHub releases.
+    fn language_server_binary_path(
+        &mut self,
+        language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> Result<String> {
+        if let Some(path) = &self.cached_binary_path {
+            if fs::metadata(path).is_ok() {
+                return Ok(path.clone());
+            }
+        }
+
+        if let Some(path) = self.local_binary_path(worktree) {
+            self.cached_binary_path = Some(path.clone());
+            return Ok(path);
+        }
+
+        self.download_binary(language_server_id)
+    }
+
+    fn local_binary_path(&self, worktree: &Worktree) -> Option<String> {
+        let (os, _arch) = zed::current_platform();
+        let binary_name = if matches!(os, Os::Windows) {
+            "typedown.exe"
+        } else {
+            "typedown"
+        };
+
+        for venv_dir in [".venv/bin", ".venv/Scripts"] {
+            let candidate = PathBuf::from(worktree.root_path())
+                .join(venv_dir)
+                .join(binary_name);
+            if fs::metadata(&candidate).is_ok() {
+                return Some(candidate.to_string_lossy().to_string());
+            }
+        }
+
+        worktree.which("typedown")
+    }
+
+    fn download_binary(&mut self, language_server_id: &LanguageServerId) -> Result<String> {
+        zed::set_language_server_installation_status(
+            language_server_id,
+            &LanguageServerInstallationStatus::CheckingForUpdate,
+        );
+
+        let release = zed::latest_github_release(
+            GITHUB_REPO,
+            GithubReleaseOptions {
+                require_assets: true,
+                pre_release: false,
+            },
+        )?;
+
+        let (os, arch) = zed::current_platform();
+        let asset_name = asset_name_for_platform(os, arch);
+        let asset = release
+            .assets
+            .iter()
+            .find(|asset| asset.name == asset_name)
+            .ok_or_else(|| format!("no asset found matching {asset_name:?}"))?;
+
+        let version_dir = format!("typedown-{}", release.version);
+        let binary_name = if matches!(os, Os::Windows) {
+            "typedown.exe"
+        } else {
+            "typedown"
+        };
+
+        if fs::metadata(&version_dir).is_err() {
+            zed::set_language_server_installation_status(
+                language_server_id,
+                &LanguageServerInstallationStatus::Downloading,
+            );
+
+            zed::download_file(
+                &asset.download_url,
+                &version_dir,
+                file_type_for_platform(os),
+            )?;
+            delete_old_versions(&version_dir);
+        }
+
+        let binary_path = find_binary(&version_dir, binary_name)
+            .ok_or_else(|| format!("could not find `{binary_name}` inside {version_dir}"))?;
+        zed::make_file_executable(&binary_path)?;
+
+        self.cached_binary_path = Some(binary_path.clone());
+        Ok(binary_path)
+    }
+}
+
+/// Recursively searches `dir` for a file named `binary_name`, since release
+/// archives don't always unpack the executable at their top level.
+fn find_binary(dir: &str, binary_name: &str) -> Option<String> {
+    for entry in fs::read_dir(dir).ok()? {
+        let entry = entry.ok()?;
+        let path = entry.path();
+        if path.is_dir() {
+            if let Some(found) = find_binary(path.to_str()?, binary_name) {
+                return Some(found);
+            }
+        } else if path.file_name().and_then(|name| name.to_str()) == Some(binary_name) {
+            return Some(path.to_string_lossy().to_string());
+        }
+    }
+    None
+}
+
+/// Removes previously downloaded `typedown-*` directories other than `current_version_dir`.
+fn delete_old_versions(current_version_dir: &str) {
+    let Ok(entries) = fs::read_dir(".") else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        let Some(name) = file_name.to_str() else {
+            continue;
+        };
+        if name.starts_with("typedown-") && name != current_version_dir {
+            fs::remove_dir_all(entry.path()).ok();
+        }
+    }
+}
+
+fn asset_name_for_platform(os: Os, arch: Architecture) -> String {
+    let os_name = match os {
+        Os::Mac => "darwin",
+        Os::Linux => "linux",
+        Os::Windows => "windows",
+    };
+    let arch_name = match arch {
+        Architecture::Aarch64 => "arm64",
+        Architecture::X8664 => "x86_64",
+        Architecture::X86 => "x86",
+    };
+    let extension = if matches!(os, Os::Windows) {
+        "zip"
+    } else {
+        "tar.gz"
+    };
+
+    format!("typedown-{os_name}-{arch_name}.{extension}")
+}
+
+fn file_type_for_platform(os: Os) -> DownloadedFileType {
+    if matches!(os, Os::Windows) {
+        DownloadedFileType::Zip
+    } else {
+        DownloadedFileType::GzipTar
+    }
+}
+
+/// Builds a `CodeLabel` that renders `code` in full and filters on `name`'s
+/// position within it, so decorated labels like `"fn foo()"` still filter on
+/// `foo` rather than the `"fn "` prefix.
+fn code_label_for_name(code: String, name: &str) -> Option<CodeLabel> {
+    let name_offset = code.find(name)?;
+    let filter_range = name_offset..name_offset + name.len();
+
+    Some(CodeLabel {
+        spans: vec![CodeLabelSpan::code_range(0..code.len())],
+        filter_range: filter_range.into(),
+        code,
+    })
+}
 
 impl Extension for TypedownExtension {
     fn new() -> Self {
-        Self
+        Self {
+            cached_binary_path: None,
+        }
     }
 
     fn language_server_command(
         &mut self,
-        _language_server_id: &LanguageServerId,
-        _worktree: &Worktree,
-    ) -> zed_extension_api::Result<Command> {
+        language_server_id: &LanguageServerId,
+        worktree: &Worktree,
+    ) -> Result<Command> {
+        let lsp_settings = LspSettings::for_worktree("typedown", worktree).ok();
+        let binary_settings = lsp_settings.and_then(|settings| settings.binary);
+
+        let command = match binary_settings.as_ref().and_then(|binary| binary.path.clone()) {
+            Some(path) => path,
+            None => self.language_server_binary_path(language_server_id, worktree)?,
+        };
+
+        let mut args = vec!["lsp".to_string()];
+        if let Some(extra_args) = binary_settings.as_ref().and_then(|binary| binary.arguments.clone()) {
+            args.extend(extra_args);
+        }
+
+        // `binary.env` mirrors Zed's documented `lsp.<name>.binary` settings schema
+        // (path/arguments/env), the same shape every built-in language server reads.
+        let mut env = worktree.shell_env();
+        if let Some(user_env) = binary_settings.and_then(|binary| binary.env) {
+            env.extend(user_env);
+        }
+
         Ok(Command {
-            command: "/Users/indenscale/Documents/Projects/Monoco/Typedown/.venv/bin/typedown".to_string(),
-            args: vec!["lsp".to_string()],
-            env: vec![],
+            command,
+            args,
+            env,
         })
     }
+
+    fn label_for_completion(
+        &self,
+        _language_server_id: &LanguageServerId,
+        completion: Completion,
+    ) -> Option<CodeLabel> {
+        let label = &completion.label;
+
+        let code = match completion.kind? {
+            CompletionKind::Function | CompletionKind::Method => format!("fn {label}()"),
+            CompletionKind::Field | CompletionKind::Property => format!("{label}:"),
+            CompletionKind::Class | CompletionKind::Struct | CompletionKind::Interface => {
+                format!("type {label}")
+            }
+            _ => label.clone(),
+        };
+
+        code_label_for_name(code, label)
+    }
+
+    fn label_for_symbol(
+        &self,
+        _language_server_id: &LanguageServerId,
+        symbol: Symbol,
+    ) -> Option<CodeLabel> {
+        let name = &symbol.name;
+
+        let code = match symbol.kind {
+            SymbolKind::Function | SymbolKind::Method => format!("fn {name}"),
+            SymbolKind::Class | SymbolKind::Struct | SymbolKind::Interface => {
+                format!("type {name}")
+            }
+            SymbolKind::Field | SymbolKind::Property | SymbolKind::Constant => {
+                format!("{name}:")
+            }
+            _ => name.clone(),
+        };
+
+        code_label_for_name(code, name)
+    }
 }
 
-zed_extension_api::register_extension!(TypedownExtension);
+zed::register_extension!(TypedownExtension);